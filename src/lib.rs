@@ -2,17 +2,18 @@ mod utils;
 
 use wasm_bindgen::prelude::*;
 use ttk91::{
-    parsing::{Context, LineSpan},
+    parsing::{Context as ParseContext, LineSpan},
     symbolic::{Program, parser::ParseError},
     symbol_table::{Label, Value},
-    emulator::{Emulator, BalloonMemory, Memory, TestIo, InputOutput},
+    emulator::{Emulator, EmulatorContext, BalloonMemory, Memory, TestIo, InputOutput},
     event::{Event, EventListener},
     source_map::SourceMap,
 };
 
-use serde_derive::Serialize;
+use serde_derive::{Serialize, Deserialize};
 use serde_json::json;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::sync::Mutex;
 use std::rc::Rc;
 
@@ -90,7 +91,7 @@ fn into_js_errors(input: &str, error: ParseError) -> Vec<JsParseError> {
     });
 
     for ctx in error.get_context() {
-        if let Context::Suggestion { span, message } = ctx {
+        if let ParseContext::Suggestion { span, message } = ctx {
             let (start_line, start_column) = calculate_position(input, span.start);
             let (end_line, end_column) = calculate_position(input, span.end);
 
@@ -123,9 +124,10 @@ pub fn parse(input: &str) -> Result<SymbolicProgram, JsValue> {
         //(err.verbose(assembly).line as u32).into())
 }
 
+#[derive(Clone)]
 struct QueueIO {
     input: Vec<i32>,
-    output: Vec<i32>, 
+    output: Vec<i32>,
     calls: Vec<u16>,
 }
 
@@ -153,6 +155,131 @@ impl QueueIO {
     }
 }
 
+/// `InputOutput` backend that bridges `IN`/`OUT`/`SVC` to JavaScript callbacks.
+/// `InputOutput` methods can't return a `Result`, so a thrown or missing
+/// callback is recorded in `error` and surfaced by `WasmEmulator::step`/`run`.
+#[derive(Clone)]
+struct JsIo {
+    on_input: Option<js_sys::Function>,
+    on_output: js_sys::Function,
+    on_supervisor_call: js_sys::Function,
+    error: Option<JsValue>,
+    last_supervisor_call: Option<u16>,
+}
+
+impl JsIo {
+    fn new(
+        on_input: Option<js_sys::Function>,
+        on_output: js_sys::Function,
+        on_supervisor_call: js_sys::Function,
+    ) -> JsIo {
+        JsIo {
+            on_input,
+            on_output,
+            on_supervisor_call,
+            error: None,
+            last_supervisor_call: None,
+        }
+    }
+
+    fn record_error(&mut self, error: JsValue) {
+        if self.error.is_none() {
+            self.error = Some(error);
+        }
+    }
+}
+
+impl InputOutput for JsIo {
+    fn input(&mut self, device: u16) -> i32 {
+        let callback = match &self.on_input {
+            Some(callback) => callback,
+            None => {
+                self.record_error(JsValue::from_serde(&json!({
+                    "error": "missing_input_callback",
+                    "device": device,
+                })).unwrap());
+
+                return 0;
+            },
+        };
+
+        match callback.call1(&JsValue::NULL, &JsValue::from(device)) {
+            Ok(value) => value.as_f64().unwrap_or(0.0) as i32,
+            Err(exception) => {
+                self.record_error(exception);
+                0
+            },
+        }
+    }
+
+    fn output(&mut self, device: u16, data: i32) {
+        if let Err(exception) = self.on_output.call2(&JsValue::NULL, &JsValue::from(device), &JsValue::from(data)) {
+            self.record_error(exception);
+        }
+    }
+
+    fn supervisor_call(&mut self, code: u16) {
+        self.last_supervisor_call = Some(code);
+
+        if let Err(exception) = self.on_supervisor_call.call1(&JsValue::NULL, &JsValue::from(code)) {
+            self.record_error(exception);
+        }
+    }
+}
+
+/// The two `InputOutput` backends a `WasmEmulator` can be created with.
+/// An enum rather than a generic parameter since `wasm_bindgen` needs
+/// `WasmEmulator` to be a monomorphic type.
+#[derive(Clone)]
+enum IoBackend {
+    Queue(QueueIO),
+    Js(JsIo),
+}
+
+impl InputOutput for IoBackend {
+    fn input(&mut self, device: u16) -> i32 {
+        match self {
+            IoBackend::Queue(io) => io.input(device),
+            IoBackend::Js(io) => io.input(device),
+        }
+    }
+
+    fn output(&mut self, device: u16, data: i32) {
+        match self {
+            IoBackend::Queue(io) => io.output(device, data),
+            IoBackend::Js(io) => io.output(device, data),
+        }
+    }
+
+    fn supervisor_call(&mut self, code: u16) {
+        match self {
+            IoBackend::Queue(io) => io.supervisor_call(code),
+            IoBackend::Js(io) => io.supervisor_call(code),
+        }
+    }
+}
+
+/// Bundles the JS callbacks used by [`create_emulator_with_io`]. `input` is
+/// optional for emulators that only drive CRT output and `SVC` halts.
+#[wasm_bindgen]
+pub struct IoCallbacks {
+    input: Option<js_sys::Function>,
+    output: js_sys::Function,
+    supervisor_call: js_sys::Function,
+}
+
+#[wasm_bindgen]
+impl IoCallbacks {
+    #[wasm_bindgen(constructor)]
+    pub fn new(output: js_sys::Function, supervisor_call: js_sys::Function, input: Option<js_sys::Function>) -> IoCallbacks {
+        IoCallbacks {
+            input,
+            output,
+            supervisor_call,
+        }
+    }
+}
+
 #[wasm_bindgen]
 pub struct Output {
     output: Vec<i32>,
@@ -162,16 +289,26 @@ pub struct Output {
 
 #[wasm_bindgen]
 impl Output {
+    /// Copies the collected output into a freshly allocated `Int32Array`,
+    /// rather than viewing WASM memory directly, since the returned array
+    /// can outlive the emulator's backing `ArrayBuffer`.
     pub fn output(&self) -> js_sys::Int32Array {
-        unsafe {
-            js_sys::Int32Array::view(self.output.as_slice())
-        }
+        js_sys::Int32Array::from(self.output.as_slice())
     }
 
+    /// Copies the collected supervisor calls into a freshly allocated `Uint16Array`.
+    /// See [`Output::output`] for why this copies rather than views.
     pub fn calls(&self) -> js_sys::Uint16Array {
-        unsafe {
-            js_sys::Uint16Array::view(self.calls.as_slice())
-        }
+        js_sys::Uint16Array::from(self.calls.as_slice())
+    }
+
+    /// Copies the collected output into a caller-provided buffer, for callers
+    /// that want to avoid the per-call allocation and manage the buffer's
+    /// lifetime themselves. Returns the number of elements written.
+    pub fn copy_output_into(&self, dst: &mut [i32]) -> usize {
+        let len = self.output.len().min(dst.len());
+        dst[..len].copy_from_slice(&self.output[..len]);
+        len
     }
 }
 
@@ -179,6 +316,8 @@ impl Output {
 struct EventRelay {
     listeners: Rc<Mutex<HashMap<String, Vec<js_sys::Function>>>>,
     universal: Rc<Mutex<Vec<js_sys::Function>>>,
+    suppressed: Rc<Mutex<bool>>,
+    touched_addresses: Rc<Mutex<HashSet<u16>>>,
 }
 
 impl EventRelay {
@@ -186,9 +325,25 @@ impl EventRelay {
         EventRelay {
             listeners: Rc::new(Mutex::new(HashMap::new())),
             universal: Rc::new(Mutex::new(Vec::new())),
+            suppressed: Rc::new(Mutex::new(false)),
+            touched_addresses: Rc::new(Mutex::new(HashSet::new())),
         }
     }
 
+    /// While suppressed, events are dropped instead of forwarded to JS
+    /// listeners. Used by `WasmEmulator::seek` to silence replayed history.
+    fn set_suppressed(&self, suppressed: bool) {
+        *self.suppressed.lock().unwrap() = suppressed;
+    }
+
+    /// Addresses written to at least once, as observed through
+    /// `Event::MemoryChange`.
+    fn touched_addresses(&self) -> Vec<u16> {
+        let mut addresses = self.touched_addresses.lock().unwrap().iter().copied().collect::<Vec<_>>();
+        addresses.sort_unstable();
+        addresses
+    }
+
     fn add_listener(&mut self, event: String, listener: js_sys::Function) {
         if event == "*" {
             self.universal
@@ -208,6 +363,14 @@ impl EventRelay {
 
 impl EventListener for EventRelay {
     fn event(&mut self, event: &Event) {
+        if let Event::MemoryChange { address, .. } = event {
+            self.touched_addresses.lock().unwrap().insert(*address);
+        }
+
+        if *self.suppressed.lock().unwrap() {
+            return;
+        }
+
         let name = match event {
             Event::SupervisorCall { .. } => "supervisor-call",
             Event::MemoryChange { .. } => "memory-change",
@@ -256,12 +419,145 @@ impl EventListener for EventRelay {
     }
 }
 
+/// The TTK91 supervisor call code that halts the program.
+const SVC_HALT: u16 = 11;
+
+/// Why `WasmEmulator::run` stopped stepping.
+#[wasm_bindgen]
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum StopReason {
+    Breakpoint,
+    Halted,
+    StepBudgetExhausted,
+    Error,
+}
+
+/// Report from `WasmEmulator::run`, letting a "Continue" button in a UI
+/// drive many steps without round-tripping through JS per instruction.
+#[wasm_bindgen]
+pub struct RunResult {
+    pub reason: StopReason,
+    pub steps_executed: u32,
+    pub line: u32,
+    output: Vec<i32>,
+    calls: Vec<u16>,
+    error: Option<String>,
+}
+
+#[wasm_bindgen]
+impl RunResult {
+    /// Copies all output the emulator has collected so far, including output
+    /// from before this run. See [`Output::output`] for why this copies
+    /// rather than views.
+    pub fn output(&self) -> js_sys::Int32Array {
+        js_sys::Int32Array::from(self.output.as_slice())
+    }
+
+    /// Copies all supervisor calls the emulator has collected so far,
+    /// including those from before this run, into a freshly allocated
+    /// `Uint16Array`.
+    pub fn calls(&self) -> js_sys::Uint16Array {
+        js_sys::Uint16Array::from(self.calls.as_slice())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn error(&self) -> Option<String> {
+        self.error.clone()
+    }
+}
+
+/// Version of the `snapshot()`/`restore()` JSON shape. Bump on shape changes
+/// so `restore` can reject snapshots it no longer knows how to read.
+const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct MemoryCellSnapshot {
+    address: u16,
+    value: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct QueueIoSnapshot {
+    input: Vec<i32>,
+    output: Vec<i32>,
+    calls: Vec<u16>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EmulatorSnapshot {
+    version: u32,
+    registers: Vec<i32>,
+    pc: u16,
+    memory_size: usize,
+    memory: Vec<MemoryCellSnapshot>,
+    io: QueueIoSnapshot,
+}
+
+/// A full snapshot of emulator state, taken every `History::interval` steps
+/// so `WasmEmulator::seek` can restore the nearest one and replay forward.
+#[derive(Clone)]
+struct Checkpoint {
+    step: u32,
+    context: EmulatorContext,
+    memory: BalloonMemory,
+    io: IoBackend,
+}
+
+/// Tracks the checkpoint stack backing `step_back`/`seek`.
+struct History {
+    checkpoints: Vec<Checkpoint>,
+    interval: u32,
+    step: u32,
+    /// Highest step ever genuinely executed (as opposed to replayed by a
+    /// `seek`). Steps up to this mark have already had their events
+    /// delivered once, so `seek` must keep suppressing them even across
+    /// multiple chained calls.
+    frontier: u32,
+}
+
+impl History {
+    fn new(interval: u32, initial: Checkpoint) -> History {
+        History {
+            checkpoints: vec![initial],
+            interval: interval.max(1),
+            step: 0,
+            frontier: 0,
+        }
+    }
+
+    fn record_if_due(&mut self, emulator: &Emulator<BalloonMemory, IoBackend>) {
+        if self.step % self.interval == 0 {
+            self.checkpoints.push(Checkpoint {
+                step: self.step,
+                context: emulator.context.clone(),
+                memory: emulator.memory.clone(),
+                io: emulator.io.clone(),
+            });
+        }
+    }
+
+    fn nearest_checkpoint(&self, step: u32) -> Checkpoint {
+        self.checkpoints.iter()
+            .rev()
+            .find(|checkpoint| checkpoint.step <= step)
+            .expect("checkpoint at step 0 is always recorded")
+            .clone()
+    }
+
+    fn discard_after(&mut self, step: u32) {
+        self.checkpoints.retain(|checkpoint| checkpoint.step <= step);
+    }
+}
+
 #[wasm_bindgen]
 pub struct WasmEmulator {
-    emulator: Emulator<BalloonMemory, QueueIO>,
+    emulator: Emulator<BalloonMemory, IoBackend>,
     source_map: SourceMap<LineSpan>,
     symbol_table: HashMap<String, u16>,
     relay: EventRelay,
+    history: History,
+    breakpoints: HashSet<u16>,
+    line_to_address: HashMap<usize, u16>,
 }
 
 #[wasm_bindgen]
@@ -278,11 +574,11 @@ impl WasmEmulator {
         self.relay.add_listener(event, listener);
     }
 
-    pub fn step(&mut self) -> Output {
-        self.emulator.step().unwrap();
-
-        let output = self.emulator.io.output.clone();
-        let calls = self.emulator.io.calls.clone();
+    fn snapshot_output(&self) -> Output {
+        let (output, calls) = match &self.emulator.io {
+            IoBackend::Queue(io) => (io.output.clone(), io.calls.clone()),
+            IoBackend::Js(_) => (Vec::new(), Vec::new()),
+        };
 
         let line = self.source_map.get_source_span(self.emulator.context.pc as usize)
             .map(|span| span.start.line)
@@ -295,6 +591,200 @@ impl WasmEmulator {
         }
     }
 
+    /// Takes the error recorded by a `JsIo` callback that threw or was
+    /// missing, if any. A no-op for the `QueueIO` backend.
+    fn take_io_error(&mut self) -> Option<JsValue> {
+        match &mut self.emulator.io {
+            IoBackend::Js(io) => io.error.take(),
+            IoBackend::Queue(_) => None,
+        }
+    }
+
+    /// Advances the emulator by one step, surfaces any error recorded by a
+    /// `JsIo` callback instead of letting it silently pass, and reports
+    /// whether this step executed the halting supervisor call.
+    fn step_checked(&mut self) -> Result<bool, JsValue> {
+        let calls_before = match &self.emulator.io {
+            IoBackend::Queue(io) => io.calls.len(),
+            IoBackend::Js(_) => 0,
+        };
+
+        if let IoBackend::Js(io) = &mut self.emulator.io {
+            io.last_supervisor_call = None;
+        }
+
+        self.emulator.step().unwrap();
+        self.history.step += 1;
+        self.history.frontier = self.history.frontier.max(self.history.step);
+        self.history.record_if_due(&self.emulator);
+
+        if let Some(error) = self.take_io_error() {
+            return Err(error);
+        }
+
+        let halted = match &self.emulator.io {
+            IoBackend::Queue(io) => io.calls.len() > calls_before && io.calls.last() == Some(&SVC_HALT),
+            IoBackend::Js(io) => io.last_supervisor_call == Some(SVC_HALT),
+        };
+
+        Ok(halted)
+    }
+
+    pub fn step(&mut self) -> Result<Output, JsValue> {
+        self.step_checked()?;
+        Ok(self.snapshot_output())
+    }
+
+    /// Rewind the emulator by a single step. Equivalent to `seek(current_step() - 1)`.
+    pub fn step_back(&mut self) -> Result<Output, JsValue> {
+        let target = self.history.step.saturating_sub(1);
+        self.seek(target)
+    }
+
+    /// Rewind or fast-forward the emulator to `step_index`, restoring the
+    /// nearest checkpoint at or before it and replaying forward with event
+    /// emission suppressed so JS listeners don't see the replay as new activity.
+    /// Unsupported for the `Js` backend, since replaying would re-invoke the
+    /// live `input`/`output`/`supervisor_call` functions for steps that already ran.
+    pub fn seek(&mut self, step_index: u32) -> Result<Output, JsValue> {
+        if matches!(self.emulator.io, IoBackend::Js(_)) {
+            return Err(JsValue::from_serde(&json!({
+                "error": "seek_unsupported_for_io_backend",
+            })).unwrap());
+        }
+
+        if step_index < self.history.step {
+            let checkpoint = self.history.nearest_checkpoint(step_index);
+
+            self.emulator.context = checkpoint.context.clone();
+            self.emulator.memory = checkpoint.memory.clone();
+            self.emulator.io = checkpoint.io.clone();
+            self.history.step = checkpoint.step;
+            self.history.discard_after(checkpoint.step);
+        }
+
+        // Only steps below `history.frontier` (the highest step ever
+        // genuinely executed, tracked across chained seeks) are replaying
+        // something that already ran once; anything from there onward is
+        // genuinely new and must still reach JS listeners.
+        let frontier = self.history.frontier;
+
+        let result = (|| {
+            while self.history.step < step_index {
+                self.relay.set_suppressed(self.history.step < frontier);
+                self.step_checked()?;
+            }
+
+            Ok::<(), JsValue>(())
+        })();
+
+        self.relay.set_suppressed(false);
+
+        result?;
+
+        Ok(self.snapshot_output())
+    }
+
+    /// Index of the current step: advanced by each `step()` and by every step
+    /// of `run`, moved by `step_back`/`seek` to the step index rewound or
+    /// fast-forwarded to, and reset to 0 by `restore`.
+    pub fn current_step(&self) -> u32 {
+        self.history.step
+    }
+
+    /// Number of checkpoints currently retained on the history stack.
+    pub fn history_len(&self) -> usize {
+        self.history.checkpoints.len()
+    }
+
+    pub fn add_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.insert(addr);
+    }
+
+    /// Resolves `line` through the program's source map and adds a
+    /// breakpoint at the first instruction belonging to it. Returns `false`
+    /// if the line has no corresponding instruction.
+    pub fn add_breakpoint_at_line(&mut self, line: usize) -> bool {
+        match self.line_to_address.get(&line) {
+            Some(&addr) => {
+                self.breakpoints.insert(addr);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Resolves `label` through the program's symbol table and adds a
+    /// breakpoint at its address. Returns `false` if the label is unknown.
+    pub fn add_breakpoint_at_label(&mut self, label: &str) -> bool {
+        match self.symbol_table.get(label).copied() {
+            Some(addr) => {
+                self.breakpoints.insert(addr);
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn remove_breakpoint(&mut self, addr: u16) -> bool {
+        self.breakpoints.remove(&addr)
+    }
+
+    /// Steps until a breakpoint address is reached, the program executes
+    /// the halting supervisor call, `max_steps` is exhausted, or stepping
+    /// errors, so a UI can implement a "Continue" button without
+    /// round-tripping per instruction.
+    pub fn run(&mut self, max_steps: u32) -> RunResult {
+        let mut steps_executed = 0;
+        let mut reason = StopReason::StepBudgetExhausted;
+        let mut error = None;
+
+        while steps_executed < max_steps {
+            match self.step_checked() {
+                Ok(halted) => {
+                    steps_executed += 1;
+
+                    if halted {
+                        reason = StopReason::Halted;
+                        break;
+                    }
+
+                    if self.breakpoints.contains(&self.emulator.context.pc) {
+                        reason = StopReason::Breakpoint;
+                        break;
+                    }
+                },
+                Err(exception) => {
+                    // `step_checked` already advanced `history.step` before
+                    // surfacing the error, so count this step too or
+                    // `steps_executed` would undercount `current_step()`.
+                    steps_executed += 1;
+                    reason = StopReason::Error;
+                    error = js_error_message(&exception);
+                    break;
+                },
+            }
+        }
+
+        let (output, calls) = match &self.emulator.io {
+            IoBackend::Queue(io) => (io.output.clone(), io.calls.clone()),
+            IoBackend::Js(_) => (Vec::new(), Vec::new()),
+        };
+
+        let line = self.source_map.get_source_span(self.emulator.context.pc as usize)
+            .map(|span| span.start.line)
+            .unwrap_or(0);
+
+        RunResult {
+            reason,
+            steps_executed,
+            line: line as u32,
+            output,
+            calls,
+            error,
+        }
+    }
+
     pub fn stack_pointer(&self) -> u16 {
         self.emulator.context.r[6] as u16
     }
@@ -306,6 +796,121 @@ impl WasmEmulator {
             })).unwrap())
     }
 
+    /// Serializes the complete machine state (registers, `pc`, touched memory
+    /// cells and the `QueueIO` queues) into a versioned JSON value. Only
+    /// supported for the `QueueIO` backend; a `JsIo`-backed emulator's state
+    /// lives partly in JS.
+    pub fn snapshot(&mut self) -> Result<JsValue, JsValue> {
+        let io = match &self.emulator.io {
+            IoBackend::Queue(io) => QueueIoSnapshot {
+                input: io.input.clone(),
+                output: io.output.clone(),
+                calls: io.calls.clone(),
+            },
+            IoBackend::Js(_) => return Err(JsValue::from_serde(&json!({
+                "error": "snapshot_unsupported_for_io_backend",
+            })).unwrap()),
+        };
+
+        let touched = self.relay.touched_addresses();
+
+        let memory = touched.iter()
+            .filter_map(|&address| {
+                self.emulator.memory.get_data(address).ok()
+                    .map(|value| MemoryCellSnapshot { address, value })
+            })
+            .collect::<Vec<_>>();
+
+        let memory_size = touched.iter().copied().max().map(|addr| addr as usize + 1).unwrap_or(0);
+
+        let snapshot = EmulatorSnapshot {
+            version: SNAPSHOT_VERSION,
+            registers: self.emulator.context.r.to_vec(),
+            pc: self.emulator.context.pc,
+            memory_size,
+            memory,
+            io,
+        };
+
+        Ok(JsValue::from_serde(&snapshot).unwrap())
+    }
+
+    /// Loads a state previously produced by `snapshot()`, replacing the
+    /// current registers, `pc`, memory cells and `QueueIO` queues. Validates
+    /// the snapshot's version and memory size before touching the live emulator.
+    pub fn restore(&mut self, state: JsValue) -> Result<(), JsValue> {
+        if !matches!(self.emulator.io, IoBackend::Queue(_)) {
+            return Err(JsValue::from_serde(&json!({
+                "error": "snapshot_unsupported_for_io_backend",
+            })).unwrap());
+        }
+
+        let snapshot: EmulatorSnapshot = state.into_serde()
+            .map_err(|_| JsValue::from_serde(&json!({
+                "error": "invalid_snapshot",
+            })).unwrap())?;
+
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(JsValue::from_serde(&json!({
+                "error": "unsupported_snapshot_version",
+                "expected": SNAPSHOT_VERSION,
+                "found": snapshot.version,
+            })).unwrap());
+        }
+
+        if snapshot.memory_size > u16::MAX as usize + 1 {
+            return Err(JsValue::from_serde(&json!({
+                "error": "invalid_memory_size",
+                "memory_size": snapshot.memory_size,
+            })).unwrap());
+        }
+
+        // Addresses touched since the snapshot but absent from it would
+        // otherwise keep their current value instead of being restored.
+        let snapshot_addresses = snapshot.memory.iter().map(|cell| cell.address).collect::<HashSet<_>>();
+
+        for address in self.relay.touched_addresses() {
+            if !snapshot_addresses.contains(&address) {
+                self.emulator.memory.set_data(address, 0)
+                    .map_err(|_| JsValue::from_serde(&json!({
+                        "error": "memory_error",
+                        "address": address,
+                    })).unwrap())?;
+            }
+        }
+
+        for cell in &snapshot.memory {
+            self.emulator.memory.set_data(cell.address, cell.value)
+                .map_err(|_| JsValue::from_serde(&json!({
+                    "error": "memory_error",
+                    "address": cell.address,
+                })).unwrap())?;
+        }
+
+        for (slot, value) in self.emulator.context.r.iter_mut().zip(snapshot.registers.iter()) {
+            *slot = *value;
+        }
+
+        self.emulator.context.pc = snapshot.pc;
+
+        self.emulator.io = IoBackend::Queue(QueueIO {
+            input: snapshot.io.input,
+            output: snapshot.io.output,
+            calls: snapshot.io.calls,
+        });
+
+        let checkpoint = Checkpoint {
+            step: 0,
+            context: self.emulator.context.clone(),
+            memory: self.emulator.memory.clone(),
+            io: self.emulator.io.clone(),
+        };
+
+        self.history = History::new(self.history.interval, checkpoint);
+
+        Ok(())
+    }
+
     /// Return an object that contains symbol names as keys and their memory
     /// addresses as the values.
     pub fn symbol_table(&self) -> JsValue {
@@ -333,8 +938,35 @@ impl WasmSourceMap {
     }
 }
 
-#[wasm_bindgen]
-pub fn create_emulator(input: &str) -> WasmEmulator {
+/// Extracts a readable message from a thrown JS value. `Error` instances
+/// store `message` on the prototype, so `JSON.stringify` on one yields `"{}"`;
+/// read `message` directly and only fall back to stringifying other values.
+fn js_error_message(exception: &JsValue) -> Option<String> {
+    if let Ok(message) = js_sys::Reflect::get(exception, &JsValue::from_str("message")) {
+        if let Some(message) = message.as_string() {
+            return Some(message);
+        }
+    }
+
+    js_sys::JSON::stringify(exception).ok().and_then(|s| s.as_string())
+}
+
+/// Builds a reverse index from source line to the address of the first
+/// instruction belonging to it, so `WasmEmulator::add_breakpoint_at_line`
+/// doesn't have to scan the source map on every call.
+fn build_line_index(source_map: &SourceMap<LineSpan>) -> HashMap<usize, u16> {
+    let mut index = HashMap::new();
+
+    for addr in 0..=u16::MAX {
+        if let Some(span) = source_map.get_source_span(addr as usize) {
+            index.entry(span.start.line).or_insert(addr);
+        }
+    }
+
+    index
+}
+
+fn build_emulator(input: &str, checkpoint_interval: u32, io: IoBackend) -> WasmEmulator {
     let program = Program::parse(input).unwrap();
     let program = program.compile();
     // let result = program.compile_sourcemap();
@@ -356,23 +988,48 @@ pub fn create_emulator(input: &str) -> WasmEmulator {
         .collect();
 
     let source_map = program.source_map.clone().into_line_based(input);
+    let line_to_address = build_line_index(&source_map);
 
     let memory = BalloonMemory::new(program);
     let relay = EventRelay::new();
 
-    let mut emulator = Emulator::new(memory, QueueIO::new())
+    let mut emulator = Emulator::new(memory, io)
         .unwrap();
 
     emulator.add_listener(relay.clone());
 
+    let initial_checkpoint = Checkpoint {
+        step: 0,
+        context: emulator.context.clone(),
+        memory: emulator.memory.clone(),
+        io: emulator.io.clone(),
+    };
+
     WasmEmulator {
         emulator,
         source_map,
         relay,
         symbol_table,
+        history: History::new(checkpoint_interval, initial_checkpoint),
+        breakpoints: HashSet::new(),
+        line_to_address,
     }
 }
 
+#[wasm_bindgen]
+pub fn create_emulator(input: &str, checkpoint_interval: u32) -> WasmEmulator {
+    build_emulator(input, checkpoint_interval, IoBackend::Queue(QueueIO::new()))
+}
+
+/// Creates an emulator whose `IN`/`OUT`/`SVC` instructions are routed to the
+/// given JS callbacks instead of the fire-and-forget `QueueIO`, so a program
+/// that reads from the keyboard or writes to the CRT can drive a real UI.
+#[wasm_bindgen]
+pub fn create_emulator_with_io(input: &str, checkpoint_interval: u32, callbacks: IoCallbacks) -> WasmEmulator {
+    let io = JsIo::new(callbacks.input, callbacks.output, callbacks.supervisor_call);
+    build_emulator(input, checkpoint_interval, IoBackend::Js(io))
+}
+
 #[wasm_bindgen]
 pub fn execute(asm: &str) -> Vec<i32> {
     let program = Program::parse(asm).unwrap();